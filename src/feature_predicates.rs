@@ -36,11 +36,11 @@ impl Display for FilePredicate {
 
 impl Predicate<str> for FilePredicate {
     fn eval(&self, actual: &str) -> bool {
-        match crate::assert_contents_impl(
-            &self.path,
-            actual,
-            crate::OverwriteMode::from_env(),
-        ) {
+        let mode = crate::OverwriteMode::from_env();
+        let result = crate::refuse_overwrite_in_ci(mode).and_then(|()| {
+            crate::assert_contents_impl(&self.path, actual, mode, &crate::MatchOptions::default())
+        });
+        match result {
             Err(e) if self.panic => {
                 panic!("assertion failed: {e}")
             }
@@ -46,6 +46,95 @@
 //! `predicates::path::eq_file` you can instead use `expectorate::eq_file` or
 //! `expectorate::eq_file_or_panic`. Populate or update the specified file as
 //! above.
+//!
+//! # Wildcard matching
+//!
+//! Sometimes the output you want to snapshot contains fragments that change
+//! from run to run, such as timestamps, temp-directory paths, or UUIDs. The
+//! [`Expectorate`] builder lets the *expected* file contain `[..]` (or a
+//! named placeholder such as `[TMP]`) anywhere on a line; at comparison time
+//! that token matches any run of characters:
+//!
+//! ```rust,ignore
+//! # fn run() -> &'static str { "wrote to /tmp/xyz/out.bin in 12ms" }
+//! let actual = run();
+//! expectorate::Expectorate::new("out.txt")
+//!     .wildcards(true)
+//!     .assert_contents(actual);
+//! ```
+//!
+//! With `EXPECTORATE=overwrite`, a naive overwrite would clobber those
+//! tokens with whatever the current run produced. [`Expectorate::redact`]
+//! turns the variable text back into its placeholder before it's written, so
+//! the expected file keeps its wildcards:
+//!
+//! ```rust,ignore
+//! # fn run() -> &'static str { "wrote to /tmp/xyz/out.bin in 12ms" }
+//! # let actual = run();
+//! expectorate::Expectorate::new("out.txt")
+//!     .wildcards(true)
+//!     .redact(regex::Regex::new(r"/tmp/\S+").unwrap(), "[TMP]")
+//!     .redact(regex::Regex::new(r"\d+ms").unwrap(), "[..]ms")
+//!     .assert_contents(actual);
+//! ```
+//!
+//! # Structural formats (feature: `json`, `toml`, `yaml`)
+//!
+//! When the expected file holds serialized data, byte-for-byte comparison
+//! makes key reordering or pretty-printing changes look like a real diff.
+//! Enable the `json` feature and set [`Format::Json`] (via
+//! [`Expectorate::format`], or the [`assert_contents_json`] shortcut) to
+//! parse both sides and compare them structurally instead:
+//!
+//! ```rust,ignore
+//! expectorate::assert_contents_json("config.json", &actual_json);
+//! ```
+//!
+//! A mismatch is reported as a diff of each side's canonical, sorted,
+//! pretty-printed form, and `EXPECTORATE=overwrite` writes that canonical
+//! form rather than `actual` verbatim, so the file stays stable across
+//! serializer versions. The `toml` and `yaml` features add
+//! [`Format::Toml`] and [`Format::Yaml`] the same way.
+//!
+//! # Diff configuration
+//!
+//! The diff shown on a mismatch can be tuned through the [`Expectorate`]
+//! builder: [`Expectorate::diff_algorithm`] picks the `similar` algorithm,
+//! [`Expectorate::context_radius`] controls how many unchanged lines of
+//! context surround a hunk, [`Expectorate::color`] forces color on or off
+//! instead of auto-detecting a tty, and [`Expectorate::word_diff`]
+//! highlights just the changed words within a replaced line instead of
+//! showing the whole line as a delete followed by an insert.
+//!
+//! # Reporting every mismatch at once
+//!
+//! `assert_contents` panics on the first mismatch, so a test touching many
+//! snapshot files stops at the first diff. [`Session`] accumulates
+//! mismatches instead, printing each one as it's found, and panics once at
+//! the end with a summary of every file that differed:
+//!
+//! ```rust,no_run
+//! let mut session = expectorate::Session::new();
+//! session.assert_contents("a.txt", "...");
+//! session.assert_contents("b.txt", "...");
+//! session.finish();
+//! ```
+//!
+//! [`try_assert_contents`] is also available directly, for callers who want
+//! to integrate the `Result` into their own test harness.
+//!
+//! # Overwrite actions
+//!
+//! `EXPECTORATE` selects an [`OverwriteMode`] beyond the default `Check`:
+//! `EXPECTORATE=overwrite` writes `actual` to the file, `EXPECTORATE=verify`
+//! treats a missing expected file as a hard failure instead of comparing
+//! against `""`, and `EXPECTORATE=create-missing` writes the file only if
+//! it doesn't exist yet, never touching one that does. `Overwrite` refuses
+//! to run (returning an error instead) when `CI` or `GITHUB_ACTIONS` is
+//! set, so a stray `EXPECTORATE=overwrite` left in a CI config fails loudly
+//! rather than silently rewriting checked-in snapshots.
+//! [`Expectorate::overwrite_mode`] overrides the mode for a single
+//! assertion regardless of the environment.
 
 #[cfg(feature = "predicates")]
 mod feature_predicates;
@@ -55,40 +144,615 @@ pub use feature_predicates::*;
 use atomicwrites::{AtomicFile, OverwriteBehavior};
 use console::Style;
 use newline_converter::dos2unix;
+use regex::Regex;
 use similar::{Algorithm, ChangeTag, TextDiff};
-use std::{env, ffi::OsStr, fs, io::Write, path::Path};
+use std::{
+    env,
+    ffi::OsStr,
+    fmt::Write as _,
+    fs,
+    io::Write,
+    path::{Path, PathBuf},
+};
 
 /// Compare the contents of the file to the string provided
 #[track_caller]
 pub fn assert_contents<P: AsRef<Path>>(path: P, actual: &str) {
-    if let Err(e) =
-        assert_contents_impl(path, actual, OverwriteMode::from_env())
-    {
+    if let Err(e) = try_assert_contents(path, actual) {
         panic!("assertion failed: {e}")
     }
 }
 
-#[derive(Clone, Copy, Debug)]
-pub(crate) enum OverwriteMode {
+/// Like [`assert_contents`], but returns the failure instead of panicking.
+pub fn try_assert_contents<P: AsRef<Path>>(path: P, actual: &str) -> Result<(), String> {
+    let mode = OverwriteMode::from_env();
+    refuse_overwrite_in_ci(mode)?;
+    assert_contents_impl(path, actual, mode, &MatchOptions::default())
+}
+
+/// Accumulates assertion failures across many [`Session::assert_contents`]
+/// calls instead of panicking on the first one, so a test touching many
+/// snapshot files can report every mismatch at once.
+///
+/// ```rust,no_run
+/// let mut session = expectorate::Session::new();
+/// session.assert_contents("a.txt", "...");
+/// session.assert_contents("b.txt", "...");
+/// session.finish();
+/// ```
+///
+/// If `finish` is never called, the accumulated failures (if any) are
+/// reported when the `Session` is dropped instead.
+#[derive(Default)]
+pub struct Session {
+    failures: Vec<PathBuf>,
+    finished: bool,
+}
+
+impl Session {
+    /// Create an empty session.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Compare the contents of the file to the string provided. Unlike
+    /// [`assert_contents`], a mismatch is printed and recorded rather than
+    /// panicking immediately; it's reported when the session finishes.
+    pub fn assert_contents<P: AsRef<Path>>(&mut self, path: P, actual: &str) {
+        if let Err(e) = try_assert_contents(&path, actual) {
+            println!("{e}");
+            self.failures.push(path.as_ref().to_path_buf());
+        }
+    }
+
+    /// Report every mismatch recorded so far, panicking once with a summary
+    /// if there were any.
+    #[track_caller]
+    pub fn finish(mut self) {
+        self.finished = true;
+        self.report();
+    }
+
+    fn report(&self) {
+        if self.failures.is_empty() {
+            return;
+        }
+        let paths = self
+            .failures
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ");
+        panic!(
+            "{} file(s) didn't match their expected contents: {paths}",
+            self.failures.len()
+        );
+    }
+}
+
+impl Drop for Session {
+    fn drop(&mut self) {
+        if !self.finished && !std::thread::panicking() {
+            self.finished = true;
+            self.report();
+        }
+    }
+}
+
+/// A builder for assertions that need more control than the zero-config
+/// [`assert_contents`] shortcut: wildcard matching, and redactions applied
+/// before an overwrite.
+pub struct Expectorate {
+    path: PathBuf,
+    mode: Option<OverwriteMode>,
+    options: MatchOptions,
+}
+
+impl Expectorate {
+    /// Start building an assertion against the contents of `path`.
+    pub fn new<P: Into<PathBuf>>(path: P) -> Self {
+        Self {
+            path: path.into(),
+            mode: None,
+            options: MatchOptions::default(),
+        }
+    }
+
+    /// Override the [`OverwriteMode`] that would otherwise come from
+    /// [`OverwriteMode::from_env`], so a test can opt into strict behavior
+    /// (for example `OverwriteMode::Verify`) regardless of the
+    /// `EXPECTORATE` environment variable.
+    pub fn overwrite_mode(mut self, mode: OverwriteMode) -> Self {
+        self.mode = Some(mode);
+        self
+    }
+
+    /// Treat bracketed tokens in the expected file (`[..]`, `[TMP]`, and so
+    /// on) as wildcards that match any run of characters on the same line,
+    /// rather than comparing lines byte-for-byte.
+    pub fn wildcards(mut self, enabled: bool) -> Self {
+        self.options.wildcards = enabled;
+        self
+    }
+
+    /// Before writing the file in `EXPECTORATE=overwrite` mode, replace text
+    /// in `actual` matching `pattern` with `placeholder`. Redactions are
+    /// applied in the order they were added, so that previously recorded
+    /// wildcard placeholders survive being overwritten with a fresh run's
+    /// output.
+    pub fn redact(mut self, pattern: Regex, placeholder: impl Into<String>) -> Self {
+        self.options.redactions.push((pattern, placeholder.into()));
+        self
+    }
+
+    /// Compare the contents of the file to the string provided, panicking on
+    /// a mismatch.
+    #[track_caller]
+    pub fn assert_contents(self, actual: &str) {
+        if let Err(e) = self.try_assert_contents(actual) {
+            panic!("assertion failed: {e}")
+        }
+    }
+
+    /// Parse both the expected file and `actual` as `format` and compare
+    /// them structurally, so that key ordering and insignificant whitespace
+    /// don't cause spurious failures. Defaults to [`Format::Text`].
+    pub fn format(mut self, format: Format) -> Self {
+        self.options.format = format;
+        self
+    }
+
+    /// Choose the `similar` diff algorithm used to render a mismatch.
+    /// Defaults to [`Algorithm::Myers`].
+    pub fn diff_algorithm(mut self, algorithm: Algorithm) -> Self {
+        self.options.diff.algorithm = algorithm;
+        self
+    }
+
+    /// Set how many unchanged lines of context surround each diff hunk.
+    /// Defaults to `5`.
+    pub fn context_radius(mut self, radius: usize) -> Self {
+        self.options.diff.context_radius = radius;
+        self
+    }
+
+    /// Force the diff to be colorized or not, instead of auto-detecting
+    /// whether stdout is a terminal. Defaults to [`ColorChoice::Auto`].
+    pub fn color(mut self, color: ColorChoice) -> Self {
+        self.options.diff.color = color;
+        self
+    }
+
+    /// Highlight just the changed words within a replaced line, instead of
+    /// showing the whole line as a delete followed by an insert. Defaults
+    /// to `false`.
+    pub fn word_diff(mut self, enabled: bool) -> Self {
+        self.options.diff.word_diff = enabled;
+        self
+    }
+
+    /// Like [`Expectorate::assert_contents`], but returns the failure
+    /// instead of panicking.
+    pub fn try_assert_contents(self, actual: &str) -> Result<(), String> {
+        let mode = match self.mode {
+            // An explicit override means the caller asked for this mode on
+            // purpose; don't second-guess it with the CI safety net below,
+            // which only exists to catch an `EXPECTORATE=overwrite` left
+            // over from local dev.
+            Some(mode) => mode,
+            None => {
+                let mode = OverwriteMode::from_env();
+                refuse_overwrite_in_ci(mode)?;
+                mode
+            }
+        };
+        assert_contents_impl(&self.path, actual, mode, &self.options)
+    }
+}
+
+/// Structural format to parse and compare the expected file and `actual`
+/// as, instead of comparing them as plain text.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Format {
+    /// Compare file contents as plain text (the default).
+    #[default]
+    Text,
+    /// Parse both sides as JSON and compare them structurally.
+    #[cfg(feature = "json")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+    Json,
+    /// Parse both sides as TOML and compare them structurally.
+    #[cfg(feature = "toml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "toml")))]
+    Toml,
+    /// Parse both sides as YAML and compare them structurally.
+    #[cfg(feature = "yaml")]
+    #[cfg_attr(docsrs, doc(cfg(feature = "yaml")))]
+    Yaml,
+}
+
+/// Parses `text` as `format` and re-serializes it in a canonical,
+/// deterministically-ordered, pretty-printed form. For [`Format::Text`],
+/// returns `text` unchanged.
+fn canonicalize(format: Format, text: &str) -> Result<String, String> {
+    match format {
+        Format::Text => Ok(text.to_string()),
+        #[cfg(feature = "json")]
+        Format::Json => {
+            let value: serde_json::Value =
+                serde_json::from_str(text).map_err(|e| format!("failed to parse as JSON: {e}"))?;
+            let mut rendered = serde_json::to_string_pretty(&value)
+                .map_err(|e| format!("failed to serialize JSON: {e}"))?;
+            rendered.push('\n');
+            Ok(rendered)
+        }
+        #[cfg(feature = "toml")]
+        Format::Toml => {
+            let value: toml::Value =
+                toml::from_str(text).map_err(|e| format!("failed to parse as TOML: {e}"))?;
+            toml::to_string_pretty(&value).map_err(|e| format!("failed to serialize TOML: {e}"))
+        }
+        #[cfg(feature = "yaml")]
+        Format::Yaml => {
+            let value: serde_yaml::Value =
+                serde_yaml::from_str(text).map_err(|e| format!("failed to parse as YAML: {e}"))?;
+            serde_yaml::to_string(&sort_yaml_mappings(value))
+                .map_err(|e| format!("failed to serialize YAML: {e}"))
+        }
+    }
+}
+
+/// Recursively sorts the keys of every mapping in `value`.
+///
+/// `serde_yaml::Mapping` is `indexmap`-backed, so it preserves the original
+/// insertion order rather than sorting it like `toml::Value` does. Without
+/// this, two YAML documents that are equivalent but list their keys in a
+/// different order would round-trip to different strings and fail a
+/// structural comparison that's supposed to ignore key order.
+#[cfg(feature = "yaml")]
+fn sort_yaml_mappings(value: serde_yaml::Value) -> serde_yaml::Value {
+    match value {
+        serde_yaml::Value::Mapping(map) => {
+            let mut entries: Vec<_> = map
+                .into_iter()
+                .map(|(k, v)| (k, sort_yaml_mappings(v)))
+                .collect();
+            entries.sort_by_key(|(k, _)| yaml_key_sort_key(k));
+            serde_yaml::Value::Mapping(entries.into_iter().collect())
+        }
+        serde_yaml::Value::Sequence(seq) => {
+            serde_yaml::Value::Sequence(seq.into_iter().map(sort_yaml_mappings).collect())
+        }
+        other => other,
+    }
+}
+
+/// Returns a string to sort a YAML mapping key by. YAML keys aren't
+/// guaranteed to be strings, so fall back to the key's serialized form.
+#[cfg(feature = "yaml")]
+fn yaml_key_sort_key(key: &serde_yaml::Value) -> String {
+    key.as_str()
+        .map(str::to_string)
+        .unwrap_or_else(|| serde_yaml::to_string(key).unwrap_or_default())
+}
+
+/// Compare the contents of the file to the string provided, parsing both
+/// sides as JSON and comparing them structurally rather than byte-for-byte.
+#[cfg(feature = "json")]
+#[cfg_attr(docsrs, doc(cfg(feature = "json")))]
+#[track_caller]
+pub fn assert_contents_json<P: Into<PathBuf>>(path: P, actual: &str) {
+    Expectorate::new(path)
+        .format(Format::Json)
+        .assert_contents(actual)
+}
+
+/// Per-assertion options that affect how the expected file is matched
+/// against `actual`, and how `actual` is rewritten on overwrite.
+#[derive(Default)]
+pub(crate) struct MatchOptions {
+    pub(crate) wildcards: bool,
+    pub(crate) redactions: Vec<(Regex, String)>,
+    pub(crate) format: Format,
+    pub(crate) diff: DiffConfig,
+}
+
+/// Controls whether diff output is colorized.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorChoice {
+    /// Colorize only when stdout looks like a terminal.
+    #[default]
+    Auto,
+    /// Always colorize.
+    Always,
+    /// Never colorize.
+    Never,
+}
+
+impl ColorChoice {
+    fn should_color(self) -> bool {
+        match self {
+            ColorChoice::Always => true,
+            ColorChoice::Never => false,
+            ColorChoice::Auto => console::Term::stdout().is_term(),
+        }
+    }
+}
+
+/// Configuration controlling how a mismatch is rendered as a diff.
+#[derive(Clone, Debug)]
+pub(crate) struct DiffConfig {
+    algorithm: Algorithm,
+    context_radius: usize,
+    color: ColorChoice,
+    word_diff: bool,
+}
+
+impl Default for DiffConfig {
+    fn default() -> Self {
+        Self {
+            algorithm: Algorithm::Myers,
+            context_radius: 5,
+            color: ColorChoice::default(),
+            word_diff: false,
+        }
+    }
+}
+
+/// Prints a unified diff between `expected` and `actual`, configured by
+/// `diff`.
+fn render_diff(expected: &str, actual: &str, diff: &DiffConfig) {
+    print!("{}", render_diff_to_string(expected, actual, diff));
+}
+
+/// Builds the unified diff between `expected` and `actual` as a string, so
+/// the rendering logic can be exercised and asserted on in tests without
+/// capturing stdout.
+fn render_diff_to_string(expected: &str, actual: &str, diff: &DiffConfig) -> String {
+    let mut out = String::new();
+    let color = diff.color.should_color();
+    for hunk in TextDiff::configure()
+        .algorithm(diff.algorithm)
+        .diff_lines(expected, actual)
+        .unified_diff()
+        .context_radius(diff.context_radius)
+        .iter_hunks()
+    {
+        writeln!(out, "{}", hunk.header()).unwrap();
+        let changes: Vec<_> = hunk.iter_changes().collect();
+        let mut i = 0;
+        while i < changes.len() {
+            // Only treat a delete/insert pair as a single modified line when
+            // each is the *only* line in its run: `similar` emits every
+            // delete in a changed block before every insert, so e.g.
+            // replacing two lines with three unrelated ones yields
+            // `Delete, Delete, Insert, Insert, Insert` rather than anything
+            // line-aligned. Pairing blindly would word-diff unrelated lines
+            // against each other.
+            let is_lone_delete = changes[i].tag() == ChangeTag::Delete
+                && (i == 0 || changes[i - 1].tag() != ChangeTag::Delete);
+            let is_lone_insert_after = i + 1 < changes.len()
+                && changes[i + 1].tag() == ChangeTag::Insert
+                && (i + 2 >= changes.len() || changes[i + 2].tag() != ChangeTag::Insert);
+
+            if diff.word_diff && is_lone_delete && is_lone_insert_after {
+                write_word_diff(&mut out, &changes[i], &changes[i + 1], color);
+                i += 2;
+                continue;
+            }
+            write_change(&mut out, &changes[i], color);
+            i += 1;
+        }
+    }
+    writeln!(out).unwrap();
+    out
+}
+
+fn write_change(out: &mut String, change: &similar::Change<&str>, color: bool) {
+    let (marker, style) = match change.tag() {
+        ChangeTag::Delete => ('-', Style::new().red().force_styling(true)),
+        ChangeTag::Insert => ('+', Style::new().green().force_styling(true)),
+        ChangeTag::Equal => (' ', Style::new().force_styling(true)),
+    };
+    if color {
+        write!(out, "{}", style.apply_to(marker).bold()).unwrap();
+        write!(out, "{}", style.apply_to(change)).unwrap();
+    } else {
+        write!(out, "{marker}{change}").unwrap();
+    }
+    if change.missing_newline() {
+        writeln!(out).unwrap();
+    }
+}
+
+/// Writes a deleted/inserted pair of lines as a single word-level diff,
+/// underlining just the words that actually changed.
+fn write_word_diff(
+    out: &mut String,
+    delete: &similar::Change<&str>,
+    insert: &similar::Change<&str>,
+    color: bool,
+) {
+    let old_style = Style::new().red().force_styling(true);
+    let new_style = Style::new().green().force_styling(true);
+    let words = TextDiff::from_words(delete.value(), insert.value());
+
+    if color {
+        write!(out, "{}", old_style.apply_to('-').bold()).unwrap();
+    } else {
+        write!(out, "-").unwrap();
+    }
+    for change in words.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Delete if color => {
+                write!(out, "{}", old_style.apply_to(change.value()).underlined()).unwrap()
+            }
+            ChangeTag::Delete | ChangeTag::Equal => write!(out, "{}", change.value()).unwrap(),
+            ChangeTag::Insert => {}
+        }
+    }
+    if delete.missing_newline() {
+        writeln!(out).unwrap();
+    }
+
+    if color {
+        write!(out, "{}", new_style.apply_to('+').bold()).unwrap();
+    } else {
+        write!(out, "+").unwrap();
+    }
+    for change in words.iter_all_changes() {
+        match change.tag() {
+            ChangeTag::Insert if color => {
+                write!(out, "{}", new_style.apply_to(change.value()).underlined()).unwrap()
+            }
+            ChangeTag::Insert | ChangeTag::Equal => write!(out, "{}", change.value()).unwrap(),
+            ChangeTag::Delete => {}
+        }
+    }
+    if insert.missing_newline() {
+        writeln!(out).unwrap();
+    }
+}
+
+/// How an assertion treats a mismatch, and a missing expected file.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OverwriteMode {
+    /// Compare the file contents to `actual`; a missing file is treated as
+    /// empty.
     Check,
+    /// Like `Check`, but a missing file is a hard failure instead of being
+    /// treated as empty. Useful in CI, where a snapshot you forgot to check
+    /// in should fail loudly rather than silently compare against `""`.
+    Verify,
+    /// Write `actual` to the file, creating or replacing it as needed.
     Overwrite,
+    /// Write `actual` only if the file doesn't exist yet; an existing file
+    /// is compared like `Check`, but never modified.
+    CreateMissing,
 }
 
 impl OverwriteMode {
-    pub(crate) fn from_env() -> Self {
-        let var = env::var_os("EXPECTORATE");
-        if var.as_deref().and_then(OsStr::to_str) == Some("overwrite") {
-            OverwriteMode::Overwrite
-        } else {
-            OverwriteMode::Check
+    /// Determines the mode from the `EXPECTORATE` environment variable:
+    /// `overwrite`, `verify`, or `create-missing` select the matching mode,
+    /// anything else (including unset) selects `Check`.
+    pub fn from_env() -> Self {
+        match env::var_os("EXPECTORATE")
+            .as_deref()
+            .and_then(OsStr::to_str)
+        {
+            Some("overwrite") => OverwriteMode::Overwrite,
+            Some("verify") => OverwriteMode::Verify,
+            Some("create-missing") => OverwriteMode::CreateMissing,
+            _ => OverwriteMode::Check,
         }
     }
 }
 
+/// Returns true if the environment looks like a CI runner, per the `CI` or
+/// `GITHUB_ACTIONS` environment variables.
+fn is_ci() -> bool {
+    env::var_os("CI").is_some() || env::var_os("GITHUB_ACTIONS").is_some()
+}
+
+/// Refuses an `EXPECTORATE=overwrite`-derived `Overwrite` mode when running
+/// in CI, so a stray environment variable left over from local dev fails
+/// loudly instead of silently rewriting checked-in snapshots. Only called
+/// where the mode was actually derived from the environment; an explicit
+/// `Overwrite` (e.g. via [`Expectorate::overwrite_mode`] or a direct
+/// `assert_contents_impl` call in a test) is left alone.
+pub(crate) fn refuse_overwrite_in_ci(mode: OverwriteMode) -> Result<(), String> {
+    if mode == OverwriteMode::Overwrite && is_ci() {
+        return Err(
+            "EXPECTORATE=overwrite is set, but this looks like a CI environment (CI or \
+             GITHUB_ACTIONS is set); run the test locally with EXPECTORATE=overwrite to update \
+             the file"
+                .to_string(),
+        );
+    }
+    Ok(())
+}
+
+/// Splits `line` on bracketed tokens like `[..]` or `[TMP]`, returning the
+/// literal segments around them. A line with no tokens yields a single
+/// segment equal to the whole line.
+fn split_on_tokens(line: &str) -> Vec<&str> {
+    let mut segments = Vec::new();
+    let mut rest = line;
+    while let Some(start) = rest.find('[') {
+        match rest[start..].find(']') {
+            Some(end) => {
+                segments.push(&rest[..start]);
+                rest = &rest[start + end + 1..];
+            }
+            None => break,
+        }
+    }
+    segments.push(rest);
+    segments
+}
+
+/// Returns true if `actual` matches `expected`, treating any bracketed
+/// tokens in `expected` as wildcards. The segment before the first token
+/// must be a prefix of `actual`, the segment after the last token must be a
+/// suffix, and the segments in between must appear in order in whatever's
+/// left over (`[..]`-style greedy-but-ordered substring matching).
+fn line_matches(expected: &str, actual: &str) -> bool {
+    let segments = split_on_tokens(expected);
+    if segments.len() == 1 {
+        return segments[0] == actual;
+    }
+
+    let first = segments[0];
+    let last = segments[segments.len() - 1];
+    if !actual.starts_with(first) || !actual.ends_with(last) {
+        return false;
+    }
+
+    let end_bound = actual.len() - last.len();
+    if first.len() > end_bound {
+        return false;
+    }
+
+    let mut cursor = first.len();
+    for segment in &segments[1..segments.len() - 1] {
+        if segment.is_empty() {
+            continue;
+        }
+        match actual[cursor..end_bound].find(segment) {
+            Some(pos) => cursor += pos + segment.len(),
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Returns true if every line of `actual` matches the corresponding line of
+/// `expected`, per [`line_matches`].
+fn wildcard_match(expected: &str, actual: &str) -> bool {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    expected_lines.len() == actual_lines.len()
+        && expected_lines
+            .iter()
+            .zip(actual_lines.iter())
+            .all(|(e, a)| line_matches(e, a))
+}
+
+/// Applies each redaction in order, replacing matches of the pattern with
+/// its placeholder.
+fn apply_redactions(actual: &str, redactions: &[(Regex, String)]) -> String {
+    let mut s = actual.to_string();
+    for (pattern, placeholder) in redactions {
+        s = pattern.replace_all(&s, placeholder.as_str()).into_owned();
+    }
+    s
+}
+
 pub(crate) fn assert_contents_impl<P: AsRef<Path>>(
     path: P,
     actual: &str,
     mode: OverwriteMode,
+    options: &MatchOptions,
 ) -> Result<(), String> {
     let path = path.as_ref();
     let actual = dos2unix(actual);
@@ -103,68 +767,103 @@ pub(crate) fn assert_contents_impl<P: AsRef<Path>>(
 
     match mode {
         OverwriteMode::Overwrite => {
-            // Don't write the file if it's the same contents. This avoids mtime
-            // invalidation.
-            if current.as_deref() != Some(&actual) {
-                // There's no way to do a compare-and-set kind of operation on
-                // filesystems where you can say "only overwrite this file if the
-                // inode matches what was just read". The closest approximation is
-                // to disallow overwrites if the file doesn't exist.
-                let behavior = if current.is_some() {
-                    OverwriteBehavior::AllowOverwrite
-                } else {
-                    OverwriteBehavior::DisallowOverwrite
-                };
-                let f = AtomicFile::new(path, behavior);
-                let res = f.write(|f| {
-                    // We're writing the contents out in one call, so there's no
-                    // need to have a BufWriter wrapper.
-                    f.write(actual.as_bytes())
-                });
-                if let Err(e) = res {
-                    panic!("unable to write to {}: {}", path.display(), e);
-                }
+            write_contents(path, &actual, current, options)?;
+        }
+        OverwriteMode::CreateMissing => {
+            if current.is_none() {
+                write_contents(path, &actual, current, options)?;
+            } else {
+                check_contents(path, current, &actual, options)?;
             }
         }
         OverwriteMode::Check => {
-            // Treat a nonexistent file like an empty file.
-            let expected_s = current.unwrap_or_default();
-            let expected = dos2unix(&expected_s);
-
-            if expected != actual {
-                for hunk in TextDiff::configure()
-                    .algorithm(Algorithm::Myers)
-                    .diff_lines(&expected, &actual)
-                    .unified_diff()
-                    .context_radius(5)
-                    .iter_hunks()
-                {
-                    println!("{}", hunk.header());
-                    for change in hunk.iter_changes() {
-                        let (marker, style) = match change.tag() {
-                            ChangeTag::Delete => ('-', Style::new().red()),
-                            ChangeTag::Insert => ('+', Style::new().green()),
-                            ChangeTag::Equal => (' ', Style::new()),
-                        };
-                        print!("{}", style.apply_to(marker).bold());
-                        print!("{}", style.apply_to(change));
-                        if change.missing_newline() {
-                            println!();
-                        }
-                    }
-                }
-                println!();
+            check_contents(path, current, &actual, options)?;
+        }
+        OverwriteMode::Verify => {
+            if current.is_none() {
                 return Err(format!(
-                    r#"string doesn't match the contents of file: "{}" see diffset above
-                set EXPECTORATE=overwrite if these changes are intentional"#,
+                    "expected file \"{}\" does not exist (EXPECTORATE=verify treats a missing \
+                     file as a failure instead of comparing against empty)",
                     path.display()
                 ));
             }
+            check_contents(path, current, &actual, options)?;
+        }
+    }
+    Ok(())
+}
+
+/// Writes `actual` (canonicalized and redacted per `options`) to `path`,
+/// skipping the write if the contents wouldn't change (this avoids mtime
+/// invalidation).
+fn write_contents(
+    path: &Path,
+    actual: &str,
+    current: Option<String>,
+    options: &MatchOptions,
+) -> Result<(), String> {
+    let canonical = canonicalize(options.format, actual)?;
+    let to_write = apply_redactions(&canonical, &options.redactions);
+    if current.as_deref() != Some(&to_write) {
+        // There's no way to do a compare-and-set kind of operation on
+        // filesystems where you can say "only overwrite this file if the
+        // inode matches what was just read". The closest approximation is
+        // to disallow overwrites if the file doesn't exist.
+        let behavior = if current.is_some() {
+            OverwriteBehavior::AllowOverwrite
+        } else {
+            OverwriteBehavior::DisallowOverwrite
+        };
+        let f = AtomicFile::new(path, behavior);
+        let res = f.write(|f| {
+            // We're writing the contents out in one call, so there's no
+            // need to have a BufWriter wrapper.
+            f.write(to_write.as_bytes())
+        });
+        if let Err(e) = res {
+            panic!("unable to write to {}: {}", path.display(), e);
         }
     }
     Ok(())
 }
 
+/// Compares `actual` to `current` (a nonexistent file is treated as empty),
+/// printing a diff and returning an error on a mismatch.
+fn check_contents(
+    path: &Path,
+    current: Option<String>,
+    actual: &str,
+    options: &MatchOptions,
+) -> Result<(), String> {
+    let expected_s = current.unwrap_or_default();
+    let expected = dos2unix(&expected_s);
+
+    let (expected, actual) = if options.format == Format::Text {
+        (expected.into_owned(), actual.to_string())
+    } else {
+        (
+            canonicalize(options.format, &expected)?,
+            canonicalize(options.format, actual)?,
+        )
+    };
+
+    let matches = if options.wildcards {
+        wildcard_match(&expected, &actual)
+    } else {
+        expected == actual
+    };
+
+    if !matches {
+        render_diff(&expected, &actual, &options.diff);
+        return Err(format!(
+            r#"string doesn't match the contents of file: "{}" see diffset above
+                set EXPECTORATE=overwrite if these changes are intentional"#,
+            path.display()
+        ));
+    }
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -189,12 +888,324 @@ mod tests {
         set_file_mtime(&path, MTIME).unwrap();
 
         // Overwrite the contents with the same value.
-        assert_contents_impl(&path, CONTENTS, OverwriteMode::Overwrite)
-            .unwrap();
+        assert_contents_impl(
+            &path,
+            CONTENTS,
+            OverwriteMode::Overwrite,
+            &MatchOptions::default(),
+        )
+        .unwrap();
 
         let meta = fs::metadata(&path).unwrap();
         let mtime2 = FileTime::from_last_modification_time(&meta);
 
         assert_eq!(mtime2, MTIME, "mtime is zero");
     }
+
+    #[test]
+    fn wildcards_match_variable_fragments() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.txt");
+        fs::write(&path, "wrote to [TMP]/out.bin in [..]ms\n").unwrap();
+
+        let actual = "wrote to /tmp/xyz/out.bin in 12ms\n";
+        let options = MatchOptions {
+            wildcards: true,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Check, &options).unwrap();
+    }
+
+    #[test]
+    fn redact_preserves_placeholders_on_overwrite() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.txt");
+        fs::write(&path, "wrote to [TMP]/out.bin\n").unwrap();
+        let actual = "wrote to /tmp/xyz/out.bin\n";
+
+        let options = MatchOptions {
+            redactions: vec![(Regex::new(r"/tmp/[^/]+").unwrap(), "[TMP]".to_string())],
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Overwrite, &options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "wrote to [TMP]/out.bin\n"
+        );
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_format_ignores_key_order() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.json");
+        fs::write(&path, "{\n  \"a\": 1,\n  \"b\": 2\n}\n").unwrap();
+
+        let actual = r#"{"b": 2, "a": 1}"#;
+        let options = MatchOptions {
+            format: Format::Json,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Check, &options).unwrap();
+    }
+
+    #[cfg(feature = "toml")]
+    #[test]
+    fn toml_format_ignores_key_order() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.toml");
+        fs::write(&path, "a = 1\nb = 2\n").unwrap();
+
+        let actual = "b = 2\na = 1\n";
+        let options = MatchOptions {
+            format: Format::Toml,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Check, &options).unwrap();
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_format_ignores_key_order() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.yaml");
+        fs::write(&path, "a: 1\nb:\n  c: 2\n  d: 3\n").unwrap();
+
+        let actual = "b:\n  d: 3\n  c: 2\na: 1\n";
+        let options = MatchOptions {
+            format: Format::Yaml,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Check, &options).unwrap();
+    }
+
+    #[test]
+    fn custom_diff_config_is_accepted() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.txt");
+        fs::write(&path, "hello world\n").unwrap();
+
+        let options = MatchOptions {
+            diff: DiffConfig {
+                algorithm: Algorithm::Patience,
+                context_radius: 1,
+                color: ColorChoice::Never,
+                word_diff: true,
+            },
+            ..Default::default()
+        };
+        let err = assert_contents_impl(&path, "hello there\n", OverwriteMode::Check, &options)
+            .unwrap_err();
+        assert!(err.contains("expected.txt"));
+    }
+
+    #[test]
+    fn word_diff_does_not_pair_unrelated_lines_across_a_block() {
+        let diff = DiffConfig {
+            word_diff: true,
+            color: ColorChoice::Never,
+            ..Default::default()
+        };
+        // Two deleted lines followed by three unrelated inserted lines: none
+        // of these should be paired up for a word-level diff. A paired
+        // word-diff would word-diff "bravo two" against "charlie three" and
+        // print them on the same line-ish output; unpaired, each line is
+        // printed on its own, plain `-`/`+` line.
+        let rendered = render_diff_to_string(
+            "alpha one\nbravo two\n",
+            "charlie three\ndelta four\nextra line\n",
+            &diff,
+        );
+        assert_eq!(
+            rendered,
+            "@@ -1,2 +1,3 @@\n\
+             -alpha one\n\
+             -bravo two\n\
+             +charlie three\n\
+             +delta four\n\
+             +extra line\n\n"
+        );
+    }
+
+    #[test]
+    fn word_diff_pairs_a_lone_one_for_one_line_replacement() {
+        let diff = DiffConfig {
+            word_diff: true,
+            color: ColorChoice::Always,
+            ..Default::default()
+        };
+        // A single changed line surrounded by unchanged ones is a true
+        // 1-for-1 replacement, so it should get word-level underlining
+        // (`\x1b[4m`) of just the changed word rather than being printed
+        // as a plain delete/insert pair.
+        let rendered = render_diff_to_string(
+            "same\nhello world\nsame\n",
+            "same\nhello there\nsame\n",
+            &diff,
+        );
+        assert_eq!(
+            rendered,
+            "@@ -1,3 +1,3 @@\n\
+             \x1b[1m \x1b[0msame\n\
+             \x1b[31m\x1b[1m-\x1b[0mhello \x1b[31m\x1b[4mworld\x1b[0m\n\
+             \x1b[32m\x1b[1m+\x1b[0mhello \x1b[32m\x1b[4mthere\x1b[0m\n\
+             \x1b[1m \x1b[0msame\n\n"
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "1 file(s) didn't match their expected contents")]
+    fn session_panics_once_with_every_mismatch() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let good = dir.path().join("good.txt");
+        let bad = dir.path().join("bad.txt");
+        fs::write(&good, "foo").unwrap();
+        fs::write(&bad, "foo").unwrap();
+
+        let mut session = Session::new();
+        session.assert_contents(&good, "foo");
+        session.assert_contents(&bad, "bar");
+        session.finish();
+    }
+
+    #[test]
+    fn session_with_no_mismatches_does_not_panic() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let good = dir.path().join("good.txt");
+        fs::write(&good, "foo").unwrap();
+
+        let mut session = Session::new();
+        session.assert_contents(&good, "foo");
+        session.finish();
+    }
+
+    #[test]
+    fn verify_mode_fails_on_missing_file() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("missing.txt");
+
+        let err = assert_contents_impl(
+            &path,
+            "foo",
+            OverwriteMode::Verify,
+            &MatchOptions::default(),
+        )
+        .unwrap_err();
+        assert!(err.contains("does not exist"));
+    }
+
+    #[test]
+    fn create_missing_writes_only_when_absent() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("maybe.txt");
+
+        // The file doesn't exist yet, so it's created.
+        assert_contents_impl(
+            &path,
+            "foo",
+            OverwriteMode::CreateMissing,
+            &MatchOptions::default(),
+        )
+        .unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+
+        // The file exists now, so a different value is a mismatch rather
+        // than being silently written.
+        assert_contents_impl(
+            &path,
+            "bar",
+            OverwriteMode::CreateMissing,
+            &MatchOptions::default(),
+        )
+        .unwrap_err();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+    }
+
+    #[test]
+    fn overwrite_refuses_to_run_in_ci() {
+        std::env::set_var("CI", "true");
+        let err = refuse_overwrite_in_ci(OverwriteMode::Overwrite).unwrap_err();
+        std::env::remove_var("CI");
+
+        assert!(err.contains("CI"));
+
+        // Other modes are never vetoed by CI detection.
+        std::env::set_var("CI", "true");
+        refuse_overwrite_in_ci(OverwriteMode::Check).unwrap();
+        std::env::remove_var("CI");
+    }
+
+    #[test]
+    fn env_derived_overwrite_refuses_to_run_in_ci() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.txt");
+        fs::write(&path, "foo").unwrap();
+
+        std::env::set_var("EXPECTORATE", "overwrite");
+        std::env::set_var("CI", "true");
+        let err = try_assert_contents(&path, "bar").unwrap_err();
+        std::env::remove_var("CI");
+        std::env::remove_var("EXPECTORATE");
+
+        assert!(err.contains("CI"));
+        assert_eq!(fs::read_to_string(&path).unwrap(), "foo");
+    }
+
+    #[test]
+    fn explicit_overwrite_mode_ignores_ci() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.txt");
+        fs::write(&path, "foo").unwrap();
+
+        std::env::set_var("CI", "true");
+        assert_contents_impl(
+            &path,
+            "bar",
+            OverwriteMode::Overwrite,
+            &MatchOptions::default(),
+        )
+        .unwrap();
+        std::env::remove_var("CI");
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "bar");
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn json_format_writes_canonical_form_on_overwrite() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.json");
+
+        let actual = r#"{"b": 2, "a": 1}"#;
+        let options = MatchOptions {
+            format: Format::Json,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Overwrite, &options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "{\n  \"a\": 1,\n  \"b\": 2\n}\n"
+        );
+    }
+
+    #[cfg(feature = "yaml")]
+    #[test]
+    fn yaml_format_writes_canonical_form_on_overwrite() {
+        let dir = TempDir::with_prefix("expectorate-").unwrap();
+        let path = dir.path().join("expected.yaml");
+
+        let actual = "b:\n  d: 3\n  c: 2\na: 1\n";
+        let options = MatchOptions {
+            format: Format::Yaml,
+            ..Default::default()
+        };
+        assert_contents_impl(&path, actual, OverwriteMode::Overwrite, &options).unwrap();
+
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            "a: 1\nb:\n  c: 2\n  d: 3\n"
+        );
+    }
 }